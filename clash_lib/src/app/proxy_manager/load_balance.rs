@@ -0,0 +1,170 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    config::internal::proxy::{LoadBalanceStrategy, OutboundGroupLoadBalance},
+    proxy::AnyOutboundHandler,
+};
+
+use super::sticky_sessions::{hash_str, StickySessionSelector};
+
+/// Virtual nodes placed per proxy on the consistent-hashing ring, matching
+/// [`StickySessionSelector`]'s so the two strategies distribute load the
+/// same way when a group switches between them.
+const VNODES_PER_PROXY: usize = 100;
+
+const DEFAULT_STICKY_TTL_SECS: u64 = 600;
+
+/// Picks which proxy in a `load-balance` group's `proxies` handles a given
+/// connection, per the group's configured [`LoadBalanceStrategy`]. Built
+/// once per group from [`crate::config::internal::proxy::OutboundGroupLoadBalance`]
+/// and reused for the group's lifetime so round-robin and sticky-sessions
+/// state persists across selections.
+pub enum LoadBalanceSelector {
+    RoundRobin { next: AtomicUsize, count: usize },
+    ConsistentHashing(Vec<(u64, usize)>),
+    StickySessions(StickySessionSelector),
+}
+
+impl LoadBalanceSelector {
+    pub fn new(
+        strategy: LoadBalanceStrategy,
+        proxy_names: &[String],
+        sticky_ttl_secs: Option<u64>,
+    ) -> Self {
+        match strategy {
+            LoadBalanceStrategy::RoundRobin => Self::RoundRobin {
+                next: AtomicUsize::new(0),
+                count: proxy_names.len(),
+            },
+            LoadBalanceStrategy::ConsistentHashing => {
+                Self::ConsistentHashing(build_ring(proxy_names))
+            }
+            LoadBalanceStrategy::StickySessions => {
+                let ttl = Duration::from_secs(sticky_ttl_secs.unwrap_or(DEFAULT_STICKY_TTL_SECS));
+                Self::StickySessions(StickySessionSelector::new(proxy_names, ttl))
+            }
+        }
+    }
+
+    /// Picks the proxy index for `(client_src_ip, dst_host)`. `is_alive`
+    /// reports whether a given proxy index is currently healthy; dead nodes
+    /// are skipped by every strategy.
+    pub fn select(
+        &self,
+        client_src_ip: &str,
+        dst_host: &str,
+        is_alive: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        match self {
+            Self::RoundRobin { next, count } => {
+                if *count == 0 {
+                    return None;
+                }
+                let start = next.fetch_add(1, Ordering::Relaxed);
+                (0..*count).map(|i| (start + i) % count).find(|idx| is_alive(*idx))
+            }
+            Self::ConsistentHashing(ring) => {
+                ring_lookup(ring, hash_str(&format!("{client_src_ip}|{dst_host}")), &is_alive)
+            }
+            Self::StickySessions(sel) => sel.select(client_src_ip, dst_host, is_alive),
+        }
+    }
+}
+
+fn build_ring(proxy_names: &[String]) -> Vec<(u64, usize)> {
+    let mut ring = Vec::with_capacity(proxy_names.len() * VNODES_PER_PROXY);
+    for (idx, name) in proxy_names.iter().enumerate() {
+        for vnode in 0..VNODES_PER_PROXY {
+            ring.push((hash_str(&format!("{name}#{vnode}")), idx));
+        }
+    }
+    ring.sort_unstable_by_key(|(h, _)| *h);
+    ring
+}
+
+/// Walks the ring clockwise from `key_hash`, returning the first alive
+/// proxy, wrapping around once if necessary.
+fn ring_lookup(
+    ring: &[(u64, usize)],
+    key_hash: u64,
+    is_alive: &impl Fn(usize) -> bool,
+) -> Option<usize> {
+    if ring.is_empty() {
+        return None;
+    }
+    let start = ring.partition_point(|(h, _)| *h < key_hash);
+    (0..ring.len())
+        .map(|i| ring[(start + i) % ring.len()].1)
+        .find(|idx| is_alive(*idx))
+}
+
+/// The `load-balance` outbound group itself: owns the proxies it picks
+/// between and the one [`LoadBalanceSelector`] built from the group's
+/// config, so `strategy`/`sticky_ttl` actually govern which proxy handles
+/// a connection instead of existing only as parsed-and-ignored config.
+pub struct LoadBalanceGroup {
+    proxies: Vec<AnyOutboundHandler>,
+    selector: LoadBalanceSelector,
+}
+
+impl LoadBalanceGroup {
+    pub fn new(cfg: &OutboundGroupLoadBalance, proxies: Vec<AnyOutboundHandler>) -> Self {
+        let proxy_names: Vec<String> = proxies.iter().map(|p| p.name().to_owned()).collect();
+        let strategy = cfg
+            .strategy
+            .unwrap_or(LoadBalanceStrategy::ConsistentHashing);
+        let selector = LoadBalanceSelector::new(strategy, &proxy_names, cfg.sticky_ttl);
+        Self { proxies, selector }
+    }
+
+    /// Picks the proxy that should carry a new connection from
+    /// `client_src_ip` to `dst_host`, skipping any proxy `is_alive` reports
+    /// as down. Call this once per connection so the group's strategy
+    /// actually drives outbound routing.
+    pub fn pick(
+        &self,
+        client_src_ip: &str,
+        dst_host: &str,
+        is_alive: impl Fn(usize) -> bool,
+    ) -> Option<&AnyOutboundHandler> {
+        let idx = self.selector.select(client_src_ip, dst_host, is_alive)?;
+        self.proxies.get(idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("proxy-{i}")).collect()
+    }
+
+    #[test]
+    fn sticky_sessions_strategy_pins_by_key() {
+        let sel = LoadBalanceSelector::new(LoadBalanceStrategy::StickySessions, &names(5), None);
+        let a = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        let b = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn round_robin_strategy_cycles_through_proxies() {
+        let sel = LoadBalanceSelector::new(LoadBalanceStrategy::RoundRobin, &names(3), None);
+        let picks: Vec<usize> = (0..6)
+            .map(|_| sel.select("1.2.3.4", "example.com", |_| true).unwrap())
+            .collect();
+        assert_eq!(picks, vec![0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn consistent_hashing_strategy_is_stable_for_same_key() {
+        let sel = LoadBalanceSelector::new(LoadBalanceStrategy::ConsistentHashing, &names(5), None);
+        let a = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        let b = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        assert_eq!(a, b);
+    }
+}
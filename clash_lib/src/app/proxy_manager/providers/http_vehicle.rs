@@ -0,0 +1,311 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use erased_serde::Serialize as ErasedSerialize;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::{
+    app::proxy_manager::healthcheck::HealthCheck,
+    config::internal::proxy::{OutboundProxyProtocol, OutboundShadowsocks},
+    proxy::AnyOutboundHandler,
+    Error,
+};
+
+use super::{proxy_provider::ProxyProvider, Provider, ProviderType, ProviderVehicleType};
+
+/// A SIP008 online-config document, as served by most Shadowsocks
+/// subscription providers. See <https://shadowsocks.org/doc/sip008.html>.
+#[derive(Deserialize)]
+struct Sip008Document {
+    #[allow(dead_code)]
+    version: u32,
+    servers: Vec<Sip008Server>,
+}
+
+#[derive(Deserialize)]
+struct Sip008Server {
+    #[allow(dead_code)]
+    id: String,
+    remarks: String,
+    server: String,
+    server_port: u16,
+    password: String,
+    method: String,
+    plugin: Option<String>,
+    plugin_opts: Option<String>,
+}
+
+/// Parses a SIP008 `plugin_opts` string (`"obfs=http;obfs-host=example.com"`)
+/// into the `key: value` map shape our Shadowsocks config expects.
+fn parse_plugin_opts(s: &str) -> HashMap<String, serde_yaml::Value> {
+    s.split(';')
+        .filter(|kv| !kv.is_empty())
+        .filter_map(|kv| kv.split_once('='))
+        .map(|(k, v)| (k.to_owned(), serde_yaml::Value::String(v.to_owned())))
+        .collect()
+}
+
+impl From<Sip008Server> for OutboundProxyProtocol {
+    fn from(s: Sip008Server) -> Self {
+        OutboundProxyProtocol::Ss(OutboundShadowsocks {
+            name: s.remarks,
+            server: s.server,
+            port: s.server_port,
+            cipher: s.method,
+            password: s.password,
+            // SIP008 has no per-server UDP flag, so default to off rather
+            // than assuming every imported server relays UDP.
+            udp: false,
+            plugin: s.plugin,
+            plugin_opts: s.plugin_opts.as_deref().map(parse_plugin_opts),
+        })
+    }
+}
+
+fn parse_sip008(body: &[u8]) -> Option<Vec<OutboundProxyProtocol>> {
+    let doc: Sip008Document = serde_json::from_slice(body).ok()?;
+    Some(doc.servers.into_iter().map(Into::into).collect())
+}
+
+/// An HTTP-backed provider vehicle that fetches a document, caches the last
+/// good copy to disk, and falls back to that cache on fetch failure so a
+/// transient network blip never empties an otherwise-working group.
+///
+/// Understands both plain Clash YAML (`proxies: [...]`) and SIP008 JSON
+/// subscriptions, trying SIP008 first since it's unambiguous (a top-level
+/// JSON object with a `servers` array) and falling back to YAML otherwise.
+pub struct HttpVehicle {
+    url: String,
+    user_agent: Option<String>,
+    headers: HashMap<String, String>,
+    cache_path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct ClashProxyList {
+    proxies: Vec<HashMap<String, serde_yaml::Value>>,
+}
+
+impl HttpVehicle {
+    pub fn new(
+        url: String,
+        user_agent: Option<String>,
+        headers: HashMap<String, String>,
+        cache_dir: &std::path::Path,
+    ) -> Self {
+        let cache_path = cache_dir.join(format!("{:x}.provider", md5::compute(url.as_bytes())));
+        Self {
+            url,
+            user_agent,
+            headers,
+            cache_path,
+        }
+    }
+
+    pub fn typ(&self) -> ProviderVehicleType {
+        ProviderVehicleType::Http
+    }
+
+    /// Fetches the document from `url`, caching it to disk on success and
+    /// falling back to the last cached copy when the fetch fails *or* when
+    /// the fetched document parses but yields zero servers -- a request
+    /// that "succeeds" with an empty list is just as capable of emptying a
+    /// working group as a network error, so it gets the same fallback.
+    pub async fn read(&self) -> Result<Vec<OutboundProxyProtocol>, Error> {
+        match self.fetch().await.and_then(|body| {
+            let parsed = Self::parse(&body)?;
+            if parsed.is_empty() {
+                Err(Error::InvalidConfig(format!(
+                    "provider {} returned a document with no servers",
+                    self.url
+                )))
+            } else {
+                Ok((body, parsed))
+            }
+        }) {
+            Ok((body, parsed)) => {
+                if let Err(e) = tokio::fs::write(&self.cache_path, &body).await {
+                    warn!("failed to cache provider {}: {}", self.url, e);
+                }
+                Ok(parsed)
+            }
+            Err(e) => {
+                warn!(
+                    "failed to fetch provider {}: {}, falling back to cache",
+                    self.url, e
+                );
+                let cached = tokio::fs::read(&self.cache_path).await.map_err(|_| e)?;
+                Self::parse(&cached)
+            }
+        }
+    }
+
+    async fn fetch(&self) -> Result<Vec<u8>, Error> {
+        let client = reqwest::Client::new();
+        let mut req = client.get(&self.url);
+        if let Some(ua) = &self.user_agent {
+            req = req.header(reqwest::header::USER_AGENT, ua.as_str());
+        }
+        for (k, v) in &self.headers {
+            req = req.header(k.as_str(), v.as_str());
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| Error::InvalidConfig(format!("failed to fetch provider: {e}")))?
+            .error_for_status()
+            .map_err(|e| Error::InvalidConfig(format!("provider returned error: {e}")))?;
+        resp.bytes()
+            .await
+            .map(|b| b.to_vec())
+            .map_err(|e| Error::InvalidConfig(format!("failed to read provider body: {e}")))
+    }
+
+    fn parse(body: &[u8]) -> Result<Vec<OutboundProxyProtocol>, Error> {
+        if let Some(servers) = parse_sip008(body) {
+            return Ok(servers);
+        }
+
+        let list: ClashProxyList = serde_yaml::from_slice(body)
+            .map_err(|e| Error::InvalidConfig(format!("invalid provider document: {e}")))?;
+        list.proxies
+            .into_iter()
+            .map(OutboundProxyProtocol::try_from)
+            .collect()
+    }
+}
+
+/// Provider-factory branch for a `use:` entry whose `vehicle-type` is
+/// `http` (the common case for a SIP008 or plain-YAML subscription URL):
+/// wires an [`HttpVehicle`] pointed at `url` into an [`HttpProvider`] that
+/// turns each document entry into a runtime handler via `converter`.
+///
+/// This is what the provider factory should call instead of constructing
+/// an [`HttpProvider`] by hand whenever a `use` provider's config resolves
+/// to [`ProviderVehicleType::Http`].
+pub fn new_http_proxy_provider(
+    name: String,
+    url: String,
+    user_agent: Option<String>,
+    headers: HashMap<String, String>,
+    cache_dir: &std::path::Path,
+    converter: fn(OutboundProxyProtocol) -> Result<AnyOutboundHandler, Error>,
+    hc: HealthCheck,
+) -> HttpProvider {
+    let vehicle = HttpVehicle::new(url, user_agent, headers, cache_dir);
+    HttpProvider::new(name, vehicle, converter, hc)
+}
+
+struct Inner {
+    proxies: Vec<AnyOutboundHandler>,
+    hc: HealthCheck,
+}
+
+/// The [`ProxyProvider`] counterpart to [`super::plain_provider::PlainProvider`]
+/// for `use`-based groups backed by a remote document: owns an
+/// [`HttpVehicle`] and periodically re-fetches it, converting each
+/// [`OutboundProxyProtocol`] into a handler via `converter` (the same
+/// conversion a config-driven `PlainProvider` would otherwise need done for
+/// it up front). A failed refresh -- fetch error or an empty document, both
+/// handled inside [`HttpVehicle::read`] -- keeps serving the last good set
+/// of proxies rather than emptying the group.
+pub struct HttpProvider {
+    name: String,
+    vehicle: HttpVehicle,
+    converter: fn(OutboundProxyProtocol) -> Result<AnyOutboundHandler, Error>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl HttpProvider {
+    pub fn new(
+        name: String,
+        vehicle: HttpVehicle,
+        converter: fn(OutboundProxyProtocol) -> Result<AnyOutboundHandler, Error>,
+        hc: HealthCheck,
+    ) -> Self {
+        Self {
+            name,
+            vehicle,
+            converter,
+            inner: Arc::new(Mutex::new(Inner {
+                proxies: vec![],
+                hc,
+            })),
+        }
+    }
+
+    async fn refresh(&self) -> Result<(), Error> {
+        let protocols = self.vehicle.read().await?;
+        let proxies = protocols
+            .into_iter()
+            .map(self.converter)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.inner.lock().await.proxies = proxies;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Provider for HttpProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn vehicle_type(&self) -> ProviderVehicleType {
+        self.vehicle.typ()
+    }
+    fn typ(&self) -> ProviderType {
+        ProviderType::Proxy
+    }
+
+    async fn initialize(&mut self) -> std::io::Result<()> {
+        self.refresh()
+            .await
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+
+        let mut inner = self.inner.lock().await;
+        if inner.hc.auto() {
+            debug!("kicking off healthcheck: {}", self.name);
+            inner.hc.kick_off();
+        }
+        Ok(())
+    }
+
+    async fn update(&self) -> std::io::Result<()> {
+        if let Err(e) = self.refresh().await {
+            warn!("failed to refresh provider {}: {}", self.name, e);
+        }
+        Ok(())
+    }
+
+    async fn as_map(&self) -> HashMap<String, Box<dyn ErasedSerialize + Send>> {
+        let mut m: HashMap<String, Box<dyn ErasedSerialize + Send>> = HashMap::new();
+        m.insert("name".to_owned(), Box::new(self.name().to_string()));
+        m.insert("type".to_owned(), Box::new(self.typ().to_string()));
+        m.insert(
+            "vehicleType".to_owned(),
+            Box::new(self.vehicle_type().to_string()),
+        );
+        let proxies =
+            futures::future::join_all(self.proxies().await.iter().map(|p| p.as_map())).await;
+        m.insert("proxies".to_owned(), Box::new(proxies));
+        m
+    }
+}
+
+#[async_trait]
+impl ProxyProvider for HttpProvider {
+    async fn proxies(&self) -> Vec<AnyOutboundHandler> {
+        self.inner.lock().await.proxies.clone()
+    }
+
+    async fn touch(&self) {
+        self.inner.lock().await.hc.touch().await;
+    }
+
+    async fn healthcheck(&self) {
+        self.inner.lock().await.hc.check().await;
+    }
+}
@@ -0,0 +1,52 @@
+use std::{collections::HashSet, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{common::filter::ProxyNameFilter, proxy::AnyOutboundHandler};
+
+use super::Provider;
+
+/// A [`Provider`] that specifically yields proxies, with the liveness hooks
+/// (`touch`/`healthcheck`) `use`-based groups drive on a schedule.
+#[async_trait]
+pub trait ProxyProvider: Provider {
+    async fn proxies(&self) -> Vec<AnyOutboundHandler>;
+    async fn touch(&self);
+    async fn healthcheck(&self);
+
+    /// The proxies this provider yields, narrowed to those whose name
+    /// matches `filter`. This is what a group's `use_provider` resolution
+    /// should call instead of [`Self::proxies`] whenever it has a
+    /// `filter:` pattern configured; with no filter, every proxy from the
+    /// provider is included, same as calling `proxies()` directly.
+    async fn filtered_proxies(&self, filter: Option<&ProxyNameFilter>) -> Vec<AnyOutboundHandler> {
+        let proxies = self.proxies().await;
+        match filter {
+            Some(f) => proxies.into_iter().filter(|p| f.is_match(p.name())).collect(),
+            None => proxies,
+        }
+    }
+}
+
+/// Resolves a `use_provider`-backed group's member list: every provider's
+/// proxies narrowed by the group's `filter:` pattern via
+/// [`ProxyProvider::filtered_proxies`], concatenated in `providers` order
+/// with later providers' duplicates (by name) against earlier ones dropped.
+///
+/// Group construction should call this instead of `proxies()` whenever it
+/// has one or more `use:` providers configured.
+pub async fn resolve_use_provider_proxies(
+    providers: &[Arc<dyn ProxyProvider>],
+    filter: Option<&ProxyNameFilter>,
+) -> Vec<AnyOutboundHandler> {
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for p in providers {
+        for proxy in p.filtered_proxies(filter).await {
+            if seen.insert(proxy.name().to_owned()) {
+                out.push(proxy);
+            }
+        }
+    }
+    out
+}
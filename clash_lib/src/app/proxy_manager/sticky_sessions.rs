@@ -0,0 +1,152 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Virtual nodes placed per proxy on the consistent-hashing ring, smoothing
+/// out the load distribution between proxies.
+const VNODES_PER_PROXY: usize = 100;
+
+/// Backs `strategy: sticky-sessions` on [`crate::config::internal::proxy::OutboundGroupLoadBalance`]:
+/// pins every `(client_src_ip, dst_host)` pair to the same upstream proxy for
+/// `ttl`, falling back to consistent hashing for the initial pick and on
+/// failover when the pinned proxy goes unhealthy.
+pub struct StickySessionSelector {
+    ring: Vec<(u64, usize)>,
+    pins: Mutex<HashMap<u64, (usize, Instant)>>,
+    ttl: Duration,
+}
+
+impl StickySessionSelector {
+    pub fn new(proxy_names: &[String], ttl: Duration) -> Self {
+        let mut ring = Vec::with_capacity(proxy_names.len() * VNODES_PER_PROXY);
+        for (idx, name) in proxy_names.iter().enumerate() {
+            for vnode in 0..VNODES_PER_PROXY {
+                ring.push((hash_str(&format!("{name}#{vnode}")), idx));
+            }
+        }
+        ring.sort_unstable_by_key(|(h, _)| *h);
+        Self {
+            ring,
+            pins: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Picks the proxy index for `(client_src_ip, dst_host)`, reusing a
+    /// live pin if one exists and hasn't expired, otherwise landing on the
+    /// ring and pinning the result. A live pin has its `ttl` refreshed on
+    /// every use, so a session that keeps talking stays pinned indefinitely
+    /// and only falls off the ring `ttl` after it goes quiet.
+    ///
+    /// `is_alive` reports whether a given proxy index is currently healthy;
+    /// dead nodes are skipped both on ring lookup and when validating an
+    /// existing pin.
+    pub fn select(
+        &self,
+        client_src_ip: &str,
+        dst_host: &str,
+        is_alive: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        let key_hash = hash_str(&format!("{client_src_ip}|{dst_host}"));
+
+        let mut pins = self.pins.lock().unwrap();
+        if let Some((idx, pinned_at)) = pins.get(&key_hash).copied() {
+            if pinned_at.elapsed() < self.ttl && is_alive(idx) {
+                pins.insert(key_hash, (idx, Instant::now()));
+                return Some(idx);
+            }
+        }
+
+        let idx = self.ring_lookup(key_hash, &is_alive)?;
+        pins.insert(key_hash, (idx, Instant::now()));
+        Some(idx)
+    }
+
+    /// Walks the ring clockwise from `key_hash`, returning the first alive
+    /// proxy, wrapping around once if necessary.
+    fn ring_lookup(&self, key_hash: u64, is_alive: &impl Fn(usize) -> bool) -> Option<usize> {
+        if self.ring.is_empty() {
+            return None;
+        }
+        let start = self.ring.partition_point(|(h, _)| *h < key_hash);
+        (0..self.ring.len())
+            .map(|i| self.ring[(start + i) % self.ring.len()].1)
+            .find(|idx| is_alive(*idx))
+    }
+}
+
+pub(crate) fn hash_str(s: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    s.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("proxy-{i}")).collect()
+    }
+
+    #[test]
+    fn same_key_picks_same_proxy() {
+        let sel = StickySessionSelector::new(&names(5), Duration::from_secs(60));
+        let a = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        let b = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn different_keys_can_land_on_different_proxies() {
+        let sel = StickySessionSelector::new(&names(20), Duration::from_secs(60));
+        let picks: std::collections::HashSet<_> = (0..50)
+            .map(|i| {
+                sel.select(&format!("10.0.0.{i}"), "example.com", |_| true)
+                    .unwrap()
+            })
+            .collect();
+        assert!(picks.len() > 1);
+    }
+
+    #[test]
+    fn rehashes_away_from_unhealthy_pin() {
+        let sel = StickySessionSelector::new(&names(5), Duration::from_secs(60));
+        let first = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        let second = sel
+            .select("1.2.3.4", "example.com", |idx| idx != first)
+            .unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn expired_pin_is_eligible_for_rehash() {
+        let sel = StickySessionSelector::new(&names(5), Duration::from_millis(1));
+        let first = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        // still resolves even though the old pin has expired
+        let second = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        assert_eq!(first, second, "ring lookup is deterministic for the same key");
+    }
+
+    #[test]
+    fn repeated_use_slides_the_ttl_forward() {
+        let sel = StickySessionSelector::new(&names(5), Duration::from_millis(20));
+        let ring_pick = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+        // Fail over once so the pin lands on a proxy the ring would not pick
+        // for this key; an expired pin would then show up as a change back.
+        let first = sel
+            .select("1.2.3.4", "example.com", |idx| idx != ring_pick)
+            .unwrap();
+        // Keep the pin alive by using it just inside the TTL, three times in
+        // a row for longer than the original TTL would have allowed.
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(12));
+            let hit = sel.select("1.2.3.4", "example.com", |_| true).unwrap();
+            assert_eq!(first, hit, "pin must still be alive: each use slides the TTL");
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! A small pattern matcher shared by anything that needs to select proxy
+//! names (or hosts) by a user-supplied string: regex when it contains
+//! syntax that only makes sense as a regex, glob when the pattern looks
+//! like one, and a plain substring match otherwise.
+
+/// A compiled `filter:` pattern, as used by proxy groups to narrow down the
+/// proxies they pull in from a [`crate::app::proxy_manager::providers::proxy_provider::ProxyProvider`].
+pub enum ProxyNameFilter {
+    Glob(glob::Pattern),
+    Regex(regex::Regex),
+    Substring(String),
+}
+
+const GLOB_META: &[char] = &['*', '?', '[', ']'];
+
+/// Characters that don't appear in an ordinary proxy name and only carry
+/// meaning in a regex (alternation, anchors, groups, escapes, quantifiers).
+/// A plain word like `"tokyo"` contains none of these and so is always
+/// treated as a literal substring rather than silently compiled as a regex
+/// that happens to also match literally.
+const REGEX_META: &[char] = &['(', ')', '|', '^', '$', '\\', '+', '{', '}'];
+
+impl ProxyNameFilter {
+    /// Compiles `pattern` into a matcher: a regex if it contains
+    /// regex-only syntax and compiles as one, a glob if it contains glob
+    /// metacharacters and compiles as one, and a plain substring match
+    /// otherwise.
+    ///
+    /// Regex-only syntax is checked first, since it's the stronger signal:
+    /// `()|^$\+{}` aren't meaningful in glob syntax, so seeing one means the
+    /// author meant a regex even if the pattern also contains `*`/`?`/`[]`
+    /// (e.g. `(jp|kr)-[0-9]+`). A pattern that contains *only* glob
+    /// metacharacters -- `jp.*`, say -- is genuinely ambiguous between "glob
+    /// wildcard" and "regex dot-star" with nothing in the string itself to
+    /// tell them apart, and is still compiled as a glob (so `.` matches
+    /// literally, not "any character") in that case.
+    pub fn new(pattern: &str) -> Self {
+        if pattern.contains(REGEX_META) {
+            if let Ok(r) = regex::Regex::new(pattern) {
+                return Self::Regex(r);
+            }
+        }
+        if pattern.contains(GLOB_META) {
+            if let Ok(p) = glob::Pattern::new(pattern) {
+                return Self::Glob(p);
+            }
+        }
+        Self::Substring(pattern.to_owned())
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        match self {
+            Self::Glob(p) => p.matches(name),
+            Self::Regex(r) => r.is_match(name),
+            Self::Substring(s) => name.contains(s.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProxyNameFilter;
+
+    #[test]
+    fn glob_matches_wildcard_pattern() {
+        let f = ProxyNameFilter::new("JP-*");
+        assert!(f.is_match("JP-Tokyo-1"));
+        assert!(!f.is_match("US-LA-1"));
+    }
+
+    #[test]
+    fn regex_matches_case_insensitive_alternation() {
+        let f = ProxyNameFilter::new("(?i)jp|japan");
+        assert!(f.is_match("Japan Node 1"));
+        assert!(f.is_match("jp-tokyo"));
+        assert!(!f.is_match("us-west"));
+    }
+
+    #[test]
+    fn regex_meta_wins_over_glob_meta_in_same_pattern() {
+        // "[0-9]" is both a glob character class and a regex character
+        // class, but "(...)" only means something in a regex, so this
+        // whole pattern must compile as a regex.
+        let f = ProxyNameFilter::new("(jp|kr)-[0-9]+");
+        assert!(matches!(f, ProxyNameFilter::Regex(_)));
+        assert!(f.is_match("jp-42"));
+        assert!(!f.is_match("us-42"));
+    }
+
+    #[test]
+    fn plain_word_never_falls_through_to_regex() {
+        // "tokyo" is also a syntactically valid regex, but since it has
+        // none of REGEX_META it must be matched as a literal substring,
+        // not silently compiled as a regex.
+        let f = ProxyNameFilter::new("tokyo");
+        assert!(matches!(f, ProxyNameFilter::Substring(_)));
+        assert!(f.is_match("jp-tokyo-1"));
+        assert!(!f.is_match("jp-osaka-1"));
+    }
+}
@@ -50,6 +50,8 @@ pub enum OutboundProxyProtocol {
     Socks5(OutboundSocks5),
     #[serde(rename = "trojan")]
     Trojan(OutboundTrojan),
+    #[serde(rename = "wireguard")]
+    Wireguard(OutboundWireguard),
 }
 
 impl OutboundProxyProtocol {
@@ -60,6 +62,7 @@ impl OutboundProxyProtocol {
             OutboundProxyProtocol::Ss(ss) => &ss.name,
             OutboundProxyProtocol::Socks5(socks5) => &socks5.name,
             OutboundProxyProtocol::Trojan(trojan) => &trojan.name,
+            OutboundProxyProtocol::Wireguard(wireguard) => &wireguard.name,
         }
     }
 }
@@ -81,6 +84,7 @@ impl Display for OutboundProxyProtocol {
             OutboundProxyProtocol::Direct => write!(f, "{}", PROXY_DIRECT),
             OutboundProxyProtocol::Reject => write!(f, "{}", PROXY_REJECT),
             OutboundProxyProtocol::Trojan(_) => write!(f, "{}", "Trojan"),
+            OutboundProxyProtocol::Wireguard(_) => write!(f, "{}", "Wireguard"),
         }
     }
 }
@@ -107,6 +111,9 @@ pub struct OutboundSocks5 {
     pub tls: bool,
     pub skip_cert_verity: bool,
     pub udp: bool,
+    /// uTLS fingerprint to imitate when `tls` is set; see
+    /// [`crate::proxy::utils::client_fingerprint::ClientFingerprint`].
+    pub client_fingerprint: Option<ClientFingerprint>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
@@ -135,6 +142,33 @@ pub struct OutboundTrojan {
     pub network: Option<String>,
     pub grpc_opts: Option<GrpcOpt>,
     pub ws_opts: Option<WsOpt>,
+    /// uTLS fingerprint to imitate in the outbound ClientHello; see
+    /// [`crate::proxy::utils::client_fingerprint::ClientFingerprint`].
+    pub client_fingerprint: Option<ClientFingerprint>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientFingerprint {
+    Chrome,
+    Firefox,
+    Safari,
+    Edge,
+    Random,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct OutboundWireguard {
+    pub name: String,
+    pub server: String,
+    pub port: u16,
+    pub private_key: String,
+    pub public_key: String,
+    pub pre_shared_key: Option<String>,
+    pub ip: Option<String>,
+    pub ipv6: Option<String>,
+    pub mtu: Option<u16>,
+    pub udp: Option<bool>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -168,8 +202,21 @@ impl TryFrom<HashMap<String, Value>> for OutboundGroupProtocol {
     type Error = Error;
 
     fn try_from(mapping: HashMap<String, Value>) -> Result<Self, Self::Error> {
-        OutboundGroupProtocol::deserialize(MapDeserializer::new(mapping.into_iter()))
-            .map_err(map_serde_error)
+        let group = OutboundGroupProtocol::deserialize(MapDeserializer::new(mapping.into_iter()))
+            .map_err(map_serde_error)?;
+
+        if let OutboundGroupProtocol::Fallback(g) = &group {
+            if g.proxies.as_ref().map_or(true, Vec::is_empty)
+                && g.use_provider.as_ref().map_or(true, Vec::is_empty)
+            {
+                return Err(Error::InvalidConfig(format!(
+                    "{}: fallback group must set at least one of proxies/use",
+                    g.name
+                )));
+            }
+        }
+
+        Ok(group)
     }
 }
 
@@ -200,6 +247,11 @@ pub struct OutboundGroupUrlTest {
     pub proxies: Option<Vec<String>>,
     #[serde(rename = "use")]
     pub use_provider: Option<Vec<String>>,
+    /// glob or regex pattern used to select which proxies sourced from
+    /// `use_provider` are included in this group; compiled with
+    /// [`crate::common::filter::ProxyNameFilter`] and applied via
+    /// [`crate::app::proxy_manager::providers::proxy_provider::ProxyProvider::filtered_proxies`].
+    pub filter: Option<String>,
 
     pub url: String,
     #[serde(deserialize_with = "utils::deserialize_u64")]
@@ -212,7 +264,11 @@ pub struct OutboundGroupUrlTest {
 pub struct OutboundGroupFallback {
     pub name: String,
 
-    pub proxies: Vec<String>,
+    pub proxies: Option<Vec<String>>,
+    #[serde(rename = "use")]
+    pub use_provider: Option<Vec<String>>,
+    pub filter: Option<String>,
+
     pub url: String,
     #[serde(deserialize_with = "utils::deserialize_u64")]
     pub interval: u64,
@@ -225,19 +281,30 @@ pub struct OutboundGroupLoadBalance {
     pub proxies: Option<Vec<String>>,
     #[serde(rename = "use")]
     pub use_provider: Option<Vec<String>>,
+    pub filter: Option<String>,
 
     pub url: String,
     #[serde(deserialize_with = "utils::deserialize_u64")]
     pub interval: u64,
+    /// Dispatched to a [`crate::app::proxy_manager::load_balance::LoadBalanceSelector`]
+    /// built from this group's `proxies`; defaults to `consistent-hashing`
+    /// when unset.
     pub strategy: Option<LoadBalanceStrategy>,
+    /// TTL, in seconds, that a `sticky-sessions` pin is kept after its last
+    /// use; each use slides the TTL forward, so only a pin that goes quiet
+    /// for this long is eligible for rehashing. Ignored by other strategies.
+    /// Defaults to 600 when unset.
+    pub sticky_ttl: Option<u64>,
 }
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy)]
 
 pub enum LoadBalanceStrategy {
     #[serde(rename = "consistent-hashing")]
     ConsistentHashing,
     #[serde(rename = "round-robin")]
     RoundRobin,
+    #[serde(rename = "sticky-sessions")]
+    StickySessions,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -247,4 +314,5 @@ pub struct OutboundGroupSelect {
     pub proxies: Option<Vec<String>>,
     #[serde(rename = "use")]
     pub use_provider: Option<Vec<String>>,
+    pub filter: Option<String>,
 }
\ No newline at end of file
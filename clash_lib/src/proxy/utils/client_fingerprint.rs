@@ -0,0 +1,143 @@
+//! Canned ClientHello shapes ("uTLS fingerprints") for a handful of popular
+//! browsers, so outbound TLS handshakes can be made to look a little less
+//! like ordinary rustls traffic.
+//!
+//! This module only owns the preset data; [`crate::proxy::utils::tls::build_connector`]
+//! is what actually applies it. Note the honest limit: rustls lets a caller
+//! choose and order its own cipher suites and key-exchange groups (via a
+//! custom `CryptoProvider`) and its ALPN list, and this preset data drives
+//! all three, but it cannot reorder the ClientHello's *extensions* --
+//! byte-for-byte extension-order mimicry needs a uTLS-capable backend that
+//! this crate doesn't depend on, so `extensions_order` is recorded for
+//! documentation/future use but isn't applied.
+
+pub use crate::config::internal::proxy::ClientFingerprint;
+
+/// The pieces of a ClientHello that differ between TLS stacks/browsers and
+/// that a passive observer commonly fingerprints (à la JA3/JA4): cipher
+/// suite order, extension order, supported groups, and ALPN.
+pub struct ClientHelloSpec {
+    pub cipher_suites: &'static [&'static str],
+    pub extensions_order: &'static [&'static str],
+    pub supported_groups: &'static [&'static str],
+    pub alpn: &'static [&'static str],
+}
+
+const CHROME: ClientHelloSpec = ClientHelloSpec {
+    cipher_suites: &[
+        "TLS13_AES_128_GCM_SHA256",
+        "TLS13_AES_256_GCM_SHA384",
+        "TLS13_CHACHA20_POLY1305_SHA256",
+        "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+    ],
+    extensions_order: &[
+        "server_name",
+        "extended_master_secret",
+        "renegotiation_info",
+        "supported_groups",
+        "ec_point_formats",
+        "session_ticket",
+        "application_layer_protocol_negotiation",
+        "status_request",
+        "signature_algorithms",
+        "signed_certificate_timestamp",
+        "key_share",
+        "psk_key_exchange_modes",
+        "supported_versions",
+        "compress_certificate",
+    ],
+    supported_groups: &["X25519", "secp256r1", "secp384r1"],
+    alpn: &["h2", "http/1.1"],
+};
+
+const FIREFOX: ClientHelloSpec = ClientHelloSpec {
+    cipher_suites: &[
+        "TLS13_AES_128_GCM_SHA256",
+        "TLS13_CHACHA20_POLY1305_SHA256",
+        "TLS13_AES_256_GCM_SHA384",
+        "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+    ],
+    extensions_order: &[
+        "server_name",
+        "extended_master_secret",
+        "renegotiation_info",
+        "supported_groups",
+        "ec_point_formats",
+        "session_ticket",
+        "application_layer_protocol_negotiation",
+        "status_request",
+        "key_share",
+        "supported_versions",
+        "signature_algorithms",
+        "psk_key_exchange_modes",
+        "record_size_limit",
+    ],
+    supported_groups: &["X25519", "secp256r1", "secp384r1", "secp521r1"],
+    alpn: &["h2", "http/1.1"],
+};
+
+const SAFARI: ClientHelloSpec = ClientHelloSpec {
+    cipher_suites: &[
+        "TLS13_AES_128_GCM_SHA256",
+        "TLS13_AES_256_GCM_SHA384",
+        "TLS13_CHACHA20_POLY1305_SHA256",
+        "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+    ],
+    extensions_order: &[
+        "server_name",
+        "extended_master_secret",
+        "renegotiation_info",
+        "supported_groups",
+        "ec_point_formats",
+        "application_layer_protocol_negotiation",
+        "status_request",
+        "signature_algorithms",
+        "key_share",
+        "psk_key_exchange_modes",
+        "supported_versions",
+    ],
+    supported_groups: &["X25519", "secp256r1", "secp384r1", "secp521r1"],
+    alpn: &["h2", "http/1.1"],
+};
+
+const EDGE: ClientHelloSpec = CHROME;
+
+const RANDOM_POOL: [ClientFingerprint; 4] = [
+    ClientFingerprint::Chrome,
+    ClientFingerprint::Firefox,
+    ClientFingerprint::Safari,
+    ClientFingerprint::Edge,
+];
+
+impl ClientFingerprint {
+    /// Resolves `Random` to one of the concrete presets, picked per-call via
+    /// the wall clock so repeated connections don't all land on the same
+    /// preset. Any other variant is returned unchanged.
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Random => {
+                let nanos = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.subsec_nanos())
+                    .unwrap_or(0);
+                RANDOM_POOL[nanos as usize % RANDOM_POOL.len()]
+            }
+            other => other,
+        }
+    }
+
+    /// The canned ClientHello shape for this preset, resolving `Random` to a
+    /// concrete preset first so this never panics.
+    pub fn spec(self) -> &'static ClientHelloSpec {
+        match self.resolve() {
+            Self::Chrome => &CHROME,
+            Self::Firefox => &FIREFOX,
+            Self::Safari => &SAFARI,
+            Self::Edge => &EDGE,
+            Self::Random => unreachable!("resolve() never returns Random"),
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod client_fingerprint;
+pub mod tls;
@@ -0,0 +1,171 @@
+//! Builds the `rustls`-backed TLS connector used by outbounds that
+//! negotiate TLS (Trojan, and the TLS path of Socks5), optionally steering
+//! it toward a named browser's cipher suite order, key-exchange group
+//! order, and ALPN list via [`ClientFingerprint`].
+
+use std::sync::Arc;
+
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring as ring_provider, CryptoProvider},
+    ClientConfig, RootCertStore,
+};
+use tokio_rustls::TlsConnector;
+
+use crate::{
+    config::internal::proxy::{OutboundSocks5, OutboundTrojan},
+    proxy::utils::client_fingerprint::ClientFingerprint,
+    Error,
+};
+
+/// Builds a [`TlsConnector`] for an outbound TLS connection.
+///
+/// `alpn` takes priority when non-empty; otherwise, if a fingerprint is set,
+/// its preset ALPN list is used. Cipher suites and key-exchange groups are
+/// reordered to match the fingerprint's preset when one is given, filtered
+/// down to whatever this build of rustls actually supports.
+pub fn build_connector(
+    alpn: &[String],
+    skip_cert_verify: bool,
+    fingerprint: Option<ClientFingerprint>,
+) -> Result<TlsConnector, Error> {
+    let mut provider = ring_provider::default_provider();
+
+    if let Some(fp) = fingerprint {
+        let spec = fp.resolve().spec();
+        provider.cipher_suites = reorder_by_name(&provider.cipher_suites, spec.cipher_suites, |s| {
+            format!("{:?}", s.suite())
+        });
+        provider.kx_groups = reorder_by_name(&provider.kx_groups, spec.supported_groups, |g| {
+            format!("{:?}", g.name())
+        });
+    }
+
+    let builder = ClientConfig::builder_with_provider(Arc::new(provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::InvalidConfig(format!("tls: unsupported protocol versions: {e}")))?;
+
+    let mut config = if skip_cert_verify {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoCertVerification))
+            .with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+
+    let alpn_protocols: Vec<String> = if !alpn.is_empty() {
+        alpn.to_vec()
+    } else {
+        fingerprint
+            .map(|fp| fp.resolve().spec().alpn.iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default()
+    };
+    config.alpn_protocols = alpn_protocols.into_iter().map(String::into_bytes).collect();
+
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// The Trojan handler's entry point: builds the connector from an
+/// `OutboundTrojan`'s own `alpn`/`skip_cert_verify`/`client_fingerprint`
+/// fields, so the handler doesn't need to know how those compose.
+pub fn build_connector_for_trojan(opts: &OutboundTrojan) -> Result<TlsConnector, Error> {
+    build_connector(
+        opts.alpn.as_deref().unwrap_or_default(),
+        opts.skip_cert_verify.unwrap_or(false),
+        opts.client_fingerprint,
+    )
+}
+
+/// The Socks5 handler's entry point for its TLS path (`tls: true`): builds
+/// the connector from an `OutboundSocks5`'s own
+/// `skip_cert_verity`/`client_fingerprint` fields. Socks5 has no `alpn`
+/// field of its own, so the fingerprint's preset ALPN list (if any) is
+/// used as-is.
+pub fn build_connector_for_socks5(opts: &OutboundSocks5) -> Result<TlsConnector, Error> {
+    build_connector(&[], opts.skip_cert_verity, opts.client_fingerprint)
+}
+
+/// Reorders `available` to put entries matching `preferred_names` (by
+/// `name_of`) first, in the order given, keeping every other available
+/// entry afterward in its original order. Names in `preferred_names` with
+/// no match in `available` are silently skipped.
+fn reorder_by_name<T: Clone>(
+    available: &[T],
+    preferred_names: &[&str],
+    name_of: impl Fn(&T) -> String,
+) -> Vec<T> {
+    let mut out = Vec::with_capacity(available.len());
+    for name in preferred_names {
+        if let Some(item) = available.iter().find(|item| name_of(item).eq_ignore_ascii_case(name)) {
+            out.push(item.clone());
+        }
+    }
+    for item in available {
+        if !out.iter().any(|o| name_of(o) == name_of(item)) {
+            out.push(item.clone());
+        }
+    }
+    out
+}
+
+/// Accepts any server certificate. Only used when the user has explicitly
+/// set `skip_cert_verify: true` on the outbound.
+#[derive(Debug)]
+struct NoCertVerification;
+
+impl ServerCertVerifier for NoCertVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &rustls::pki_types::CertificateDer<'_>,
+        _dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &rustls::pki_types::CertificateDer<'_>,
+        _dss: &rustls::DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        ring_provider::default_provider().signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reorder_puts_named_entries_first() {
+        let available = vec!["a", "b", "c", "d"];
+        let reordered = reorder_by_name(&available, &["c", "a"], |s| s.to_string());
+        assert_eq!(reordered, vec!["c", "a", "b", "d"]);
+    }
+
+    #[test]
+    fn reorder_skips_unknown_preferred_names() {
+        let available = vec!["a", "b"];
+        let reordered = reorder_by_name(&available, &["nope", "b"], |s| s.to_string());
+        assert_eq!(reordered, vec!["b", "a"]);
+    }
+}
@@ -0,0 +1,343 @@
+//! A tiny userspace netstack that sits on top of a [`Tunnel`]: WireGuard
+//! only ever sees opaque, encrypted transport-data packets whose plaintext
+//! payload is a raw IP packet, so something has to speak TCP/IP on our side
+//! of the tunnel to turn that into ordinary byte streams. `smoltcp` plays
+//! that role; this module just wires its virtual [`Device`] to the
+//! tunnel's `send`/`recv` and exposes `connect_tcp`/`bind_udp`.
+
+use std::{
+    collections::VecDeque,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+};
+
+use smoltcp::{
+    iface::{Config, Interface, SocketHandle, SocketSet},
+    phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken},
+    socket::{tcp, udp},
+    time::Instant as SmolInstant,
+    wire::{HardwareAddress, IpAddress, IpCidr},
+};
+use tokio::sync::{mpsc, Notify};
+
+use crate::Error;
+
+use super::Tunnel;
+
+/// A `smoltcp` device backed by in-memory queues rather than a real NIC:
+/// packets `smoltcp` wants to send are pushed onto `outbound` for the pump
+/// loop to encrypt and hand to the tunnel; packets decrypted off the
+/// tunnel are pushed onto `inbound` for `smoltcp` to parse.
+struct QueueDevice {
+    inbound: VecDeque<Vec<u8>>,
+    outbound: mpsc::UnboundedSender<Vec<u8>>,
+    mtu: u16,
+}
+
+struct RxT(Vec<u8>);
+impl RxToken for RxT {
+    fn consume<R, F: FnOnce(&mut [u8]) -> R>(mut self, f: F) -> R {
+        f(&mut self.0)
+    }
+}
+
+struct TxT<'a>(&'a mpsc::UnboundedSender<Vec<u8>>);
+impl<'a> TxToken for TxT<'a> {
+    fn consume<R, F: FnOnce(&mut [u8]) -> R>(self, len: usize, f: F) -> R {
+        let mut buf = vec![0u8; len];
+        let r = f(&mut buf);
+        let _ = self.0.send(buf);
+        r
+    }
+}
+
+impl Device for QueueDevice {
+    type RxToken<'a> = RxT;
+    type TxToken<'a> = TxT<'a>;
+
+    fn receive(
+        &mut self,
+        _timestamp: SmolInstant,
+    ) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let pkt = self.inbound.pop_front()?;
+        Some((RxT(pkt), TxT(&self.outbound)))
+    }
+
+    fn transmit(&mut self, _timestamp: SmolInstant) -> Option<Self::TxToken<'_>> {
+        Some(TxT(&self.outbound))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.max_transmission_unit = self.mtu as usize;
+        caps.medium = Medium::Ip;
+        caps
+    }
+}
+
+/// Owns the `smoltcp` interface and socket set and runs the pump loop that
+/// shuttles packets between them and the [`Tunnel`]. `Handle`s created via
+/// `connect_tcp`/`bind_udp` talk to it over channels so callers never touch
+/// `smoltcp` directly.
+pub struct NetStack {
+    inner: Arc<Mutex<Inner>>,
+    poke: Arc<Notify>,
+}
+
+struct Inner {
+    iface: Interface,
+    device: QueueDevice,
+    sockets: SocketSet<'static>,
+}
+
+impl NetStack {
+    /// Brings up a virtual interface with `local_ip` as its only address
+    /// and a default route through the tunnel, then spawns the background
+    /// tasks that drive packets between the tunnel and the interface.
+    pub fn new(tunnel: Arc<Tunnel>, local_ip: IpAddr, mtu: u16) -> Result<Arc<Self>, Error> {
+        let (outbound_tx, mut outbound_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let mut device = QueueDevice {
+            inbound: VecDeque::new(),
+            outbound: outbound_tx,
+            mtu,
+        };
+
+        let mut config = Config::new(HardwareAddress::Ip);
+        config.random_seed = rand_seed();
+        let mut iface = Interface::new(config, &mut device, SmolInstant::from_secs(0));
+        iface.update_ip_addrs(|addrs| {
+            addrs
+                .push(IpCidr::new(IpAddress::from(local_ip), if local_ip.is_ipv4() { 32 } else { 128 }))
+                .expect("fresh address list has room for one entry");
+        });
+        match local_ip {
+            IpAddr::V4(v4) => iface.routes_mut().add_default_ipv4_route(v4.into()),
+            IpAddr::V6(v6) => iface.routes_mut().add_default_ipv6_route(v6.into()),
+        }
+        .expect("fresh route table has room for a default route");
+        let stack = Arc::new(Self {
+            inner: Arc::new(Mutex::new(Inner {
+                iface,
+                device,
+                sockets: SocketSet::new(vec![]),
+            })),
+            poke: Arc::new(Notify::new()),
+        });
+
+        // Drains smoltcp's outbound queue and ships each packet out over
+        // the WireGuard tunnel.
+        {
+            let tunnel = tunnel.clone();
+            tokio::spawn(async move {
+                while let Some(pkt) = outbound_rx.recv().await {
+                    if let Err(e) = tunnel.send(&pkt).await {
+                        tracing::warn!("wireguard netstack: failed to send packet: {e}");
+                    }
+                }
+            });
+        }
+
+        // Pulls decrypted packets off the tunnel and feeds them to smoltcp,
+        // then drives the interface's timers/retransmits forward.
+        {
+            let stack = stack.clone();
+            tokio::spawn(async move {
+                loop {
+                    match tunnel.recv().await {
+                        Ok(pkt) => {
+                            let mut inner = stack.inner.lock().unwrap();
+                            inner.device.inbound.push_back(pkt);
+                            Self::poll_locked(&mut inner);
+                            stack.poke.notify_waiters();
+                        }
+                        Err(e) => {
+                            tracing::warn!("wireguard netstack: failed to receive packet: {e}");
+                        }
+                    }
+                }
+            });
+        }
+
+        // Ticks the interface periodically even with no inbound traffic,
+        // so retransmits and connection timeouts still fire.
+        {
+            let stack = stack.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(std::time::Duration::from_millis(100));
+                loop {
+                    interval.tick().await;
+                    let mut inner = stack.inner.lock().unwrap();
+                    Self::poll_locked(&mut inner);
+                    stack.poke.notify_waiters();
+                }
+            });
+        }
+
+        Ok(stack)
+    }
+
+    fn poll_locked(inner: &mut Inner) {
+        let timestamp = SmolInstant::now();
+        inner.iface.poll(timestamp, &mut inner.device, &mut inner.sockets);
+    }
+
+    /// Opens a TCP connection to `remote` through the tunnel, returning a
+    /// handle as soon as the SYN is queued; the handshake completes in the
+    /// background, and the handle's `send`/`receive` wait for it.
+    pub async fn connect_tcp(self: &Arc<Self>, remote: SocketAddr) -> Result<TcpHandle, Error> {
+        let handle = {
+            let mut inner = self.inner.lock().unwrap();
+            let rx_buf = tcp::SocketBuffer::new(vec![0u8; 64 * 1024]);
+            let tx_buf = tcp::SocketBuffer::new(vec![0u8; 64 * 1024]);
+            let mut socket = tcp::Socket::new(rx_buf, tx_buf);
+            let local_port = 20000 + (rand_seed() % 10000) as u16;
+            socket
+                .connect(
+                    inner.iface.context(),
+                    (IpAddress::from(remote.ip()), remote.port()),
+                    local_port,
+                )
+                .map_err(|e| Error::InvalidConfig(format!("wireguard tcp connect: {e:?}")))?;
+            let handle = inner.sockets.add(socket);
+            Self::poll_locked(&mut inner);
+            handle
+        };
+        self.poke.notify_waiters();
+
+        Ok(TcpHandle {
+            stack: self.clone(),
+            handle,
+        })
+    }
+
+    /// Allocates a UDP socket bound locally, for datagram-oriented
+    /// tunneling (e.g. DNS-over-WireGuard).
+    pub async fn bind_udp(self: &Arc<Self>) -> Result<UdpHandle, Error> {
+        let handle = {
+            let mut inner = self.inner.lock().unwrap();
+            let rx_meta = udp::PacketMetadata::EMPTY;
+            let tx_meta = udp::PacketMetadata::EMPTY;
+            let rx_buf = udp::PacketBuffer::new(vec![rx_meta; 32], vec![0u8; 64 * 1024]);
+            let tx_buf = udp::PacketBuffer::new(vec![tx_meta; 32], vec![0u8; 64 * 1024]);
+            let mut socket = udp::Socket::new(rx_buf, tx_buf);
+            let local_port = 20000 + (rand_seed() % 10000) as u16;
+            socket
+                .bind(local_port)
+                .map_err(|e| Error::InvalidConfig(format!("wireguard udp bind: {e:?}")))?;
+            inner.sockets.add(socket)
+        };
+        Ok(UdpHandle {
+            stack: self.clone(),
+            handle,
+        })
+    }
+}
+
+fn rand_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// A TCP stream tunneled through WireGuard, with plain async `send`/`receive`
+/// that poll the owning [`NetStack`]'s socket set.
+/// [`super::stream::WireguardStream`] adapts it to [`tokio::io::AsyncRead`]/
+/// [`tokio::io::AsyncWrite`].
+pub struct TcpHandle {
+    stack: Arc<NetStack>,
+    handle: SocketHandle,
+}
+
+impl TcpHandle {
+    pub async fn send(&self, buf: &[u8]) -> Result<usize, Error> {
+        loop {
+            {
+                let mut inner = self.stack.inner.lock().unwrap();
+                let socket = inner.sockets.get_mut::<tcp::Socket>(self.handle);
+                if socket.can_send() {
+                    let n = socket
+                        .send_slice(buf)
+                        .map_err(|e| Error::InvalidConfig(format!("wireguard tcp send: {e:?}")))?;
+                    NetStack::poll_locked(&mut inner);
+                    return Ok(n);
+                }
+                if !socket.is_open() {
+                    return Err(Error::InvalidConfig("wireguard tcp connection closed".to_owned()));
+                }
+            }
+            self.stack.poke.notified().await;
+        }
+    }
+
+    pub async fn receive(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        loop {
+            {
+                let mut inner = self.stack.inner.lock().unwrap();
+                let socket = inner.sockets.get_mut::<tcp::Socket>(self.handle);
+                if socket.can_recv() {
+                    let n = socket
+                        .recv_slice(buf)
+                        .map_err(|e| Error::InvalidConfig(format!("wireguard tcp recv: {e:?}")))?;
+                    NetStack::poll_locked(&mut inner);
+                    return Ok(n);
+                }
+                if !socket.is_open() {
+                    return Ok(0);
+                }
+            }
+            self.stack.poke.notified().await;
+        }
+    }
+}
+
+impl Drop for TcpHandle {
+    fn drop(&mut self) {
+        let mut inner = self.stack.inner.lock().unwrap();
+        inner.sockets.remove(self.handle);
+    }
+}
+
+/// A UDP "connection" tunneled through WireGuard, scoped to a single peer
+/// address per send/receive call (WireGuard's own framing, not ours, is
+/// what keeps datagrams separated between different outer peers).
+pub struct UdpHandle {
+    stack: Arc<NetStack>,
+    handle: SocketHandle,
+}
+
+impl UdpHandle {
+    pub async fn send_to(&self, buf: &[u8], dst: SocketAddr) -> Result<(), Error> {
+        let mut inner = self.stack.inner.lock().unwrap();
+        let socket = inner.sockets.get_mut::<udp::Socket>(self.handle);
+        socket
+            .send_slice(buf, (IpAddress::from(dst.ip()), dst.port()))
+            .map_err(|e| Error::InvalidConfig(format!("wireguard udp send: {e:?}")))?;
+        NetStack::poll_locked(&mut inner);
+        Ok(())
+    }
+
+    pub async fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddr), Error> {
+        loop {
+            {
+                let mut inner = self.stack.inner.lock().unwrap();
+                let socket = inner.sockets.get_mut::<udp::Socket>(self.handle);
+                if socket.can_recv() {
+                    let (n, meta) = socket
+                        .recv_slice(buf)
+                        .map_err(|e| Error::InvalidConfig(format!("wireguard udp recv: {e:?}")))?;
+                    let addr = SocketAddr::new(meta.endpoint.addr.into(), meta.endpoint.port);
+                    return Ok((n, addr));
+                }
+            }
+            self.stack.poke.notified().await;
+        }
+    }
+}
+
+impl Drop for UdpHandle {
+    fn drop(&mut self) {
+        let mut inner = self.stack.inner.lock().unwrap();
+        inner.sockets.remove(self.handle);
+    }
+}
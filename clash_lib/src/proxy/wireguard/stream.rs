@@ -0,0 +1,117 @@
+//! Adapts a [`super::netstack::TcpHandle`] (whose `send`/`receive` are
+//! plain `async fn`s) to [`tokio::io::AsyncRead`]/[`tokio::io::AsyncWrite`],
+//! so a tunneled TCP connection can be handed to callers expecting an
+//! ordinary byte stream.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::netstack::TcpHandle;
+
+type PendingRead = Pin<Box<dyn Future<Output = io::Result<Vec<u8>>> + Send>>;
+type PendingWrite = Pin<Box<dyn Future<Output = io::Result<usize>> + Send>>;
+
+/// A tunneled TCP connection, readable/writable like any other socket.
+pub struct WireguardStream {
+    handle: Arc<TcpHandle>,
+    read_fut: Option<PendingRead>,
+    // Bytes from a completed read future not yet drained by the caller,
+    // since a single `receive()` call may return more than `buf` can hold.
+    read_buf: VecDeque<u8>,
+    write_fut: Option<PendingWrite>,
+}
+
+fn to_io_err(e: crate::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+impl WireguardStream {
+    pub fn new(handle: TcpHandle) -> Self {
+        Self {
+            handle: Arc::new(handle),
+            read_fut: None,
+            read_buf: VecDeque::new(),
+            write_fut: None,
+        }
+    }
+}
+
+impl AsyncRead for WireguardStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if !self.read_buf.is_empty() {
+            let n = self.read_buf.len().min(buf.remaining());
+            let chunk: Vec<u8> = self.read_buf.drain(..n).collect();
+            buf.put_slice(&chunk);
+            return Poll::Ready(Ok(()));
+        }
+
+        if self.read_fut.is_none() {
+            let handle = self.handle.clone();
+            self.read_fut = Some(Box::pin(async move {
+                let mut tmp = vec![0u8; 64 * 1024];
+                let n = handle.receive(&mut tmp).await.map_err(to_io_err)?;
+                tmp.truncate(n);
+                Ok(tmp)
+            }));
+        }
+
+        match self.read_fut.as_mut().unwrap().as_mut().poll(cx) {
+            Poll::Ready(Ok(data)) => {
+                self.read_fut = None;
+                let n = data.len().min(buf.remaining());
+                buf.put_slice(&data[..n]);
+                self.read_buf.extend(&data[n..]);
+                Poll::Ready(Ok(()))
+            }
+            Poll::Ready(Err(e)) => {
+                self.read_fut = None;
+                Poll::Ready(Err(e))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl AsyncWrite for WireguardStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.write_fut.is_none() {
+            let handle = self.handle.clone();
+            let owned = buf.to_vec();
+            self.write_fut = Some(Box::pin(async move {
+                handle.send(&owned).await.map_err(to_io_err)
+            }));
+        }
+
+        match self.write_fut.as_mut().unwrap().as_mut().poll(cx) {
+            Poll::Ready(result) => {
+                self.write_fut = None;
+                Poll::Ready(result)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}